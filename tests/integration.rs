@@ -0,0 +1,3029 @@
+//! Integration tests against a live PostgreSQL database
+//!
+//! These tests only run when `POSTGRES_TEST_CONNECTION_STRING` is set, e.g.
+//!
+//! ```bash
+//! POSTGRES_TEST_CONNECTION_STRING="host=localhost user=postgres dbname=postgres" cargo test
+//! ```
+
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::CallToolResult;
+use rmcp_postgres::*;
+
+/// Server for the test database, or `None` if no test database is configured
+fn test_server() -> Option<PostgresServer> {
+    std::env::var("POSTGRES_TEST_CONNECTION_STRING")
+        .ok()
+        .map(PostgresServer::new)
+}
+
+/// Parse the JSON text content of a tool result
+fn json_result(result: CallToolResult) -> serde_json::Value {
+    let text = &result.content[0].as_text().expect("text content").text;
+    serde_json::from_str(text).expect("valid JSON")
+}
+
+#[tokio::test]
+async fn test_sum_bigint_beyond_i64_range() {
+    let Some(server) = test_server() else { return };
+
+    let result = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT SUM(v) AS total
+                    FROM (VALUES (9223372036854775807::bigint), (9223372036854775807::bigint)) AS t(v)"
+                .to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+
+    let json = json_result(result);
+    assert_eq!(json["rows"][0]["total"], "18446744073709551614");
+}
+
+#[tokio::test]
+async fn test_integer_widths() {
+    let Some(server) = test_server() else { return };
+
+    let result = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT 42::smallint AS status,
+                           -7::integer AS delta,
+                           9007199254740993::bigint AS big,
+                           0.5::real AS ratio,
+                           NULL::smallint AS missing"
+                .to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+
+    let json = json_result(result);
+    let row = &json["rows"][0];
+    assert_eq!(row["status"], 42);
+    assert_eq!(row["delta"], -7);
+    assert_eq!(row["big"], 9007199254740993i64);
+    assert_eq!(row["ratio"], 0.5);
+    assert!(row["missing"].is_null());
+}
+
+#[tokio::test]
+async fn test_numeric_round_trips_exactly() {
+    let Some(server) = test_server() else { return };
+
+    let result = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT 1234567890.123456789::decimal(30, 9) AS amount,
+                           -10.500::numeric AS refund,
+                           NULL::numeric AS fee"
+                .to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+
+    let json = json_result(result);
+    let row = &json["rows"][0];
+    assert_eq!(row["amount"], "1234567890.123456789");
+    assert_eq!(row["refund"], "-10.500");
+    assert!(row["fee"].is_null());
+}
+
+#[tokio::test]
+async fn test_system_identifier_types() {
+    let Some(server) = test_server() else { return };
+
+    let result = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT 'pg_catalog.pg_class'::regclass::oid AS class_oid,
+                           'pg_catalog.pg_class'::regclass AS class_reg,
+                           'int4'::regtype AS type_reg,
+                           '42'::xid AS xid"
+                .to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+
+    let json = json_result(result);
+    let row = &json["rows"][0];
+    // pg_class and int4 have fixed OIDs in every PostgreSQL installation
+    assert_eq!(row["class_oid"], 1259);
+    assert_eq!(row["class_reg"], 1259);
+    assert_eq!(row["type_reg"], 23);
+    assert_eq!(row["xid"], 42);
+}
+
+#[tokio::test]
+async fn test_timetz_includes_offset() {
+    let Some(server) = test_server() else { return };
+
+    let result = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT '14:30:00+02'::timetz AS starts_at,
+                           '09:15:30.25-05:30'::timetz AS ends_at"
+                .to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+
+    let json = json_result(result);
+    let row = &json["rows"][0];
+    assert_eq!(row["starts_at"], "14:30:00+02:00");
+    assert_eq!(row["ends_at"], "09:15:30.25-05:30");
+}
+
+#[tokio::test]
+async fn test_timestamps_are_iso8601() {
+    let Some(server) = test_server() else { return };
+
+    let result = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT '2024-01-02 03:04:05.5'::timestamp AS created_at,
+                           '2024-01-02 03:04:05+02'::timestamptz AS updated_at,
+                           NULL::timestamptz AS deleted_at"
+                .to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+
+    let json = json_result(result);
+    let row = &json["rows"][0];
+    assert_eq!(row["created_at"], "2024-01-02T03:04:05.500");
+    assert_eq!(row["updated_at"], "2024-01-02T01:04:05Z");
+    assert!(row["deleted_at"].is_null());
+}
+
+#[tokio::test]
+async fn test_date_and_time_columns() {
+    let Some(server) = test_server() else { return };
+
+    let result = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT '2024-02-29'::date AS day,
+                           '08:15:00'::time AS opens,
+                           '23:59:59.123456'::time AS closes,
+                           NULL::date AS holiday"
+                .to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+
+    let json = json_result(result);
+    let row = &json["rows"][0];
+    assert_eq!(row["day"], "2024-02-29");
+    assert_eq!(row["opens"], "08:15:00");
+    assert_eq!(row["closes"], "23:59:59.123456");
+    assert!(row["holiday"].is_null());
+}
+
+#[tokio::test]
+async fn test_bytea_is_base64() {
+    let Some(server) = test_server() else { return };
+
+    let result = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT E'\\\\xDEADBEEF'::bytea AS payload, NULL::bytea AS empty".to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+
+    let json = json_result(result);
+    assert_eq!(json["rows"][0]["payload"], "3q2+7w==");
+    assert!(json["rows"][0]["empty"].is_null());
+}
+
+#[tokio::test]
+async fn test_json_columns_are_nested() {
+    let Some(server) = test_server() else { return };
+
+    let result = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT '{\"a\":1}'::jsonb AS doc,
+                           '[1, \"two\", null]'::json AS list,
+                           NULL::jsonb AS missing"
+                .to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+
+    let json = json_result(result);
+    let row = &json["rows"][0];
+    assert_eq!(row["doc"], serde_json::json!({"a": 1}));
+    assert_eq!(row["list"], serde_json::json!([1, "two", null]));
+    assert!(row["missing"].is_null());
+}
+
+#[tokio::test]
+async fn test_array_columns() {
+    let Some(server) = test_server() else { return };
+
+    let result = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT ARRAY[1, 2, 3] AS ids,
+                           '{a,b}'::text[] AS tags,
+                           ARRAY[true, NULL]::bool[] AS flags,
+                           '{}'::bigint[] AS empty,
+                           '{{1.5,2},{3,4}}'::float8[] AS matrix"
+                .to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+
+    let json = json_result(result);
+    let row = &json["rows"][0];
+    assert_eq!(row["ids"], serde_json::json!([1, 2, 3]));
+    assert_eq!(row["tags"], serde_json::json!(["a", "b"]));
+    assert_eq!(row["flags"], serde_json::json!([true, null]));
+    assert_eq!(row["empty"], serde_json::json!([]));
+    assert_eq!(row["matrix"], serde_json::json!([[1.5, 2.0], [3.0, 4.0]]));
+}
+
+#[tokio::test]
+async fn test_uuid_columns() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute(
+        "CREATE TABLE IF NOT EXISTS rmcp_test_uuids (
+             id uuid PRIMARY KEY,
+             related uuid[]
+         )",
+    )
+    .await
+    .unwrap();
+    execute(
+        "INSERT INTO rmcp_test_uuids VALUES
+             ('a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11',
+              ARRAY['b1ffcd00-0d1c-4ef8-bb6d-6bb9bd380a22'::uuid, NULL])
+         ON CONFLICT DO NOTHING",
+    )
+    .await
+    .unwrap();
+
+    let sample = server
+        .get_table_sample(Parameters(TableSampleParams {
+            table_name: "rmcp_test_uuids".to_string(),
+            limit: None,
+            exclude_columns: None,
+            offset: None,
+            order_by: None,
+            where_conditions: None,
+        }))
+        .await;
+
+    execute("DROP TABLE rmcp_test_uuids").await.unwrap();
+
+    let json = json_result(sample.unwrap());
+    let row = &json["rows"][0];
+    assert_eq!(row["id"], "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11");
+    assert_eq!(
+        row["related"],
+        serde_json::json!(["b1ffcd00-0d1c-4ef8-bb6d-6bb9bd380a22", null])
+    );
+}
+
+#[tokio::test]
+async fn test_self_join_keeps_duplicate_columns() {
+    let Some(server) = test_server() else { return };
+
+    let result = server
+        .query_data(Parameters(QueryParams {
+            query: "WITH nodes(id, parent_id) AS (VALUES (1::bigint, NULL::bigint), (2, 1))
+                    SELECT child.id, parent.id
+                    FROM nodes child JOIN nodes parent ON child.parent_id = parent.id"
+                .to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+
+    let json = json_result(result);
+    let row = &json["rows"][0];
+    assert_eq!(row["id"], 2);
+    assert_eq!(row["id_2"], 1);
+}
+
+#[tokio::test]
+async fn test_infer_column_semantics() {
+    let Some(server) = test_server() else { return };
+
+    server
+        .execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: "CREATE TABLE IF NOT EXISTS rmcp_test_semantics AS
+                    SELECT * FROM (VALUES
+                        ('alice@example.com', 'https://example.com/a', 'SKU-1'),
+                        ('bob@example.org', 'http://example.org', 'SKU-2'),
+                        ('carol@example.net', 'https://example.net/x?y=1', 'SKU-3')
+                    ) AS t(email, homepage, sku)"
+                .to_string(),
+            params: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+
+    let infer = |column: &str| {
+        server.infer_column_semantics(Parameters(InferSemanticsParams {
+            table_name: "rmcp_test_semantics".to_string(),
+            column_name: column.to_string(),
+            sample_size: None,
+            custom_patterns: Some([("sku".to_string(), "^SKU-[0-9]+$".to_string())].into()),
+        }))
+    };
+
+    let email = json_result(infer("email").await.unwrap());
+    let homepage = json_result(infer("homepage").await.unwrap());
+    let sku = json_result(infer("sku").await.unwrap());
+
+    server
+        .execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: "DROP TABLE rmcp_test_semantics".to_string(),
+            params: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+
+    assert_eq!(email["semantic_type"], "email");
+    assert_eq!(email["candidates"][0]["confidence"], 1.0);
+    assert_eq!(email["sampled_values"], 3);
+    assert_eq!(homepage["semantic_type"], "url");
+    assert_eq!(sku["semantic_type"], "sku");
+}
+
+#[tokio::test]
+async fn test_count_rows_binds_condition_values() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute("CREATE TABLE IF NOT EXISTS rmcp_test_count (name text, age int, joined date)")
+        .await
+        .unwrap();
+    execute(
+        "INSERT INTO rmcp_test_count VALUES
+             ('O''Brien', 30, '2024-01-01'),
+             ('O''Brien', 41, NULL),
+             ('Smith', 30, '2024-01-01')",
+    )
+    .await
+    .unwrap();
+
+    let count = |conditions: serde_json::Value| {
+        server.count_rows(Parameters(CountRowsParams {
+            table_name: "rmcp_test_count".to_string(),
+            where_conditions: Some(conditions),
+            approximate: None,
+        }))
+    };
+
+    let by_name = count(serde_json::json!({"name": "O'Brien"})).await;
+    let by_age = count(serde_json::json!({"age": 30})).await;
+    let by_date = count(serde_json::json!({"joined": "2024-01-01", "name": "Smith"})).await;
+    let by_null = count(serde_json::json!({"joined": null})).await;
+
+    execute("DROP TABLE rmcp_test_count").await.unwrap();
+
+    assert_eq!(json_result(by_name.unwrap())["count"], 2);
+    assert_eq!(json_result(by_age.unwrap())["count"], 2);
+    assert_eq!(json_result(by_date.unwrap())["count"], 1);
+    assert_eq!(json_result(by_null.unwrap())["count"], 1);
+}
+
+#[tokio::test]
+async fn test_count_rows_approximate() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let count = |where_conditions: Option<serde_json::Value>| {
+        server.count_rows(Parameters(CountRowsParams {
+            table_name: "rmcp_test_count_estimate".to_string(),
+            where_conditions,
+            approximate: Some(true),
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_count_estimate").await.unwrap();
+    execute("CREATE TABLE rmcp_test_count_estimate (id int)").await.unwrap();
+    execute("INSERT INTO rmcp_test_count_estimate SELECT generate_series(1, 1000)").await.unwrap();
+    execute("ANALYZE rmcp_test_count_estimate").await.unwrap();
+    // Rows added after ANALYZE only show up in an exact count
+    execute("INSERT INTO rmcp_test_count_estimate SELECT generate_series(1001, 1010)").await.unwrap();
+
+    let estimated = count(None).await;
+    let filtered = count(Some(serde_json::json!({"id": 5}))).await;
+
+    execute("DROP TABLE rmcp_test_count_estimate").await.unwrap();
+    let missing = count(None).await;
+
+    assert_eq!(
+        json_result(estimated.unwrap()),
+        serde_json::json!({"table_name": "rmcp_test_count_estimate", "count": 1000, "approximate": true})
+    );
+    let filtered = json_result(filtered.unwrap());
+    assert_eq!(filtered["count"], 1);
+    assert_eq!(filtered["approximate"], false);
+    assert!(missing.is_err());
+}
+
+#[tokio::test]
+async fn test_update_data_binds_values_and_honors_limit() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute("CREATE TABLE IF NOT EXISTS rmcp_test_update (id int, qty int, status text)")
+        .await
+        .unwrap();
+    execute("INSERT INTO rmcp_test_update SELECT n, 0, 'new' FROM generate_series(1, 5) AS n")
+        .await
+        .unwrap();
+
+    let update = |values: serde_json::Value, conditions: serde_json::Value, limit: Option<i32>| {
+        server.update_data(Parameters(UpdateDataParams {
+            table_name: "rmcp_test_update".to_string(),
+            values,
+            where_conditions: conditions,
+            limit,
+            returning: None,
+            connection_override: None,
+        }))
+    };
+
+    let single = update(serde_json::json!({"qty": 5}), serde_json::json!({"id": 1}), None).await;
+    let capped = update(
+        serde_json::json!({"status": "done"}),
+        serde_json::json!({"status": "new"}),
+        Some(3),
+    )
+    .await;
+    let check = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT (SELECT qty FROM rmcp_test_update WHERE id = 1) AS qty,
+                           (SELECT COUNT(*) FROM rmcp_test_update WHERE status = 'done') AS done"
+                .to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await;
+
+    execute("DROP TABLE rmcp_test_update").await.unwrap();
+
+    assert_eq!(json_result(single.unwrap())["rows_affected"], 1);
+    assert_eq!(json_result(capped.unwrap())["rows_affected"], 3);
+    let row = &json_result(check.unwrap())["rows"][0];
+    assert_eq!(row["qty"], 5);
+    assert_eq!(row["done"], 3);
+}
+
+#[tokio::test]
+async fn test_delete_data_binds_values_and_honors_limit() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute("CREATE TABLE IF NOT EXISTS rmcp_test_delete (id int, batch int)")
+        .await
+        .unwrap();
+    execute("INSERT INTO rmcp_test_delete SELECT n, n % 2 FROM generate_series(1, 10) AS n")
+        .await
+        .unwrap();
+
+    let delete = |conditions: serde_json::Value, limit: Option<i32>| {
+        server.delete_data(Parameters(DeleteDataParams {
+            table_name: "rmcp_test_delete".to_string(),
+            where_conditions: conditions,
+            limit,
+            returning: None,
+            connection_override: None,
+        }))
+    };
+
+    let by_id = delete(serde_json::json!({"id": 4}), None).await;
+    let capped = delete(serde_json::json!({"batch": 1}), Some(2)).await;
+    let remaining = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT COUNT(*) AS total,
+                           COUNT(*) FILTER (WHERE id = 4) AS id_4,
+                           COUNT(*) FILTER (WHERE batch = 1) AS odd
+                    FROM rmcp_test_delete"
+                .to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await;
+
+    execute("DROP TABLE rmcp_test_delete").await.unwrap();
+
+    assert_eq!(json_result(by_id.unwrap())["rows_affected"], 1);
+    assert_eq!(json_result(capped.unwrap())["rows_affected"], 2);
+    let row = &json_result(remaining.unwrap())["rows"][0];
+    assert_eq!(row["total"], 7);
+    assert_eq!(row["id_4"], 0);
+    assert_eq!(row["odd"], 3);
+}
+
+#[tokio::test]
+async fn test_data_tools_quote_identifiers() {
+    let Some(server) = test_server() else { return };
+
+    // A mixed-case table name containing a double quote, with a reserved-word column
+    let table = "Odd \"Name\"";
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute(r#"CREATE TABLE IF NOT EXISTS "Odd ""Name""" ("select" text, "Order" text)"#)
+        .await
+        .unwrap();
+
+    let inserted = server
+        .insert_data(Parameters(InsertParams {
+            table_name: table.to_string(),
+            data: serde_json::json!({"select": "a", "Order": "first"}),
+            returning: None,
+            connection_override: None,
+        }))
+        .await;
+    let updated = server
+        .update_data(Parameters(UpdateDataParams {
+            table_name: table.to_string(),
+            values: serde_json::json!({"Order": "second"}),
+            where_conditions: serde_json::json!({"select": "a"}),
+            limit: None,
+            returning: None,
+            connection_override: None,
+        }))
+        .await;
+    let counted = server
+        .count_rows(Parameters(CountRowsParams {
+            table_name: table.to_string(),
+            where_conditions: Some(serde_json::json!({"Order": "second"})),
+            approximate: None,
+        }))
+        .await;
+    let sampled = server
+        .get_table_sample(Parameters(TableSampleParams {
+            table_name: table.to_string(),
+            limit: None,
+            exclude_columns: None,
+            offset: None,
+            order_by: None,
+            where_conditions: None,
+        }))
+        .await;
+    let deleted = server
+        .delete_data(Parameters(DeleteDataParams {
+            table_name: table.to_string(),
+            where_conditions: serde_json::json!({"select": "a"}),
+            limit: None,
+            returning: None,
+            connection_override: None,
+        }))
+        .await;
+
+    execute(r#"DROP TABLE "Odd ""Name""""#).await.unwrap();
+
+    inserted.unwrap();
+    assert_eq!(json_result(updated.unwrap())["rows_affected"], 1);
+    assert_eq!(json_result(counted.unwrap())["count"], 1);
+    assert_eq!(json_result(sampled.unwrap())["rows"][0]["Order"], "second");
+    assert_eq!(json_result(deleted.unwrap())["rows_affected"], 1);
+}
+
+#[tokio::test]
+async fn test_pooled_connections_are_reused_and_reset() {
+    let Some(connection_string) = std::env::var("POSTGRES_TEST_CONNECTION_STRING").ok() else { return };
+    let server = PostgresServer::new(connection_string).with_pool_size(1);
+
+    let query = |query: &str| {
+        server.query_data(Parameters(QueryParams {
+            query: query.to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+    };
+
+    let first = json_result(query("SELECT pg_backend_pid() AS pid").await.unwrap());
+    server
+        .execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: "SET application_name = 'leaked'".to_string(),
+            params: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+    let second = json_result(
+        query("SELECT pg_backend_pid() AS pid, current_setting('application_name') AS app")
+            .await
+            .unwrap(),
+    );
+
+    assert_eq!(first["rows"][0]["pid"], second["rows"][0]["pid"]);
+    assert_ne!(second["rows"][0]["app"], "leaked");
+}
+
+/// Connects through TLS when `POSTGRES_TEST_TLS_CONNECTION_STRING` points at a server
+/// (or role) that rejects plain connections, e.g. with `sslmode=verify-full sslrootcert=...`
+#[tokio::test]
+async fn test_tls_connection() {
+    let Some(connection_string) = std::env::var("POSTGRES_TEST_TLS_CONNECTION_STRING").ok() else { return };
+    let server = PostgresServer::new(connection_string);
+
+    let result = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT ssl FROM pg_stat_ssl WHERE pid = pg_backend_pid()".to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+
+    assert_eq!(json_result(result)["rows"][0]["ssl"], true);
+}
+
+#[tokio::test]
+async fn test_read_only_allows_select() {
+    let Ok(connection_string) = std::env::var("POSTGRES_TEST_CONNECTION_STRING") else {
+        return;
+    };
+    let server = PostgresServer::new_read_only(connection_string);
+
+    let result = server
+        .execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: "SELECT 1 AS one".to_string(),
+            params: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+
+    assert_eq!(json_result(result)["rows"][0]["one"], 1);
+}
+
+#[tokio::test]
+async fn test_explain_query() {
+    let Some(server) = test_server() else { return };
+
+    let explain = |query: &str, analyze: bool| {
+        server.explain_query(Parameters(ExplainParams {
+            query: query.to_string(),
+            analyze: Some(analyze),
+            connection_override: None,
+        }))
+    };
+
+    let plain = json_result(explain("SELECT * FROM pg_class WHERE oid = 1259", false).await.unwrap());
+    assert!(plain["plan"]["Node Type"].is_string());
+    assert!(plain["planning_time_ms"].is_number());
+    assert!(plain["execution_time_ms"].is_null());
+
+    server
+        .execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: "CREATE TABLE IF NOT EXISTS rmcp_test_explain (id int)".to_string(),
+            params: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+    let analyzed = explain("INSERT INTO rmcp_test_explain VALUES (1);", true).await;
+    let count = server
+        .count_rows(Parameters(CountRowsParams {
+            table_name: "rmcp_test_explain".to_string(),
+            where_conditions: None,
+            approximate: None,
+        }))
+        .await;
+    server
+        .execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: "DROP TABLE rmcp_test_explain".to_string(),
+            params: None,
+            connection_override: None,
+        }))
+        .await
+        .unwrap();
+
+    let analyzed = json_result(analyzed.unwrap());
+    assert_eq!(analyzed["plan"]["Node Type"], "ModifyTable");
+    assert!(analyzed["execution_time_ms"].is_number());
+    assert_eq!(json_result(count.unwrap())["count"], 0);
+}
+
+#[tokio::test]
+async fn test_transaction_rollback_discards_changes() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let count = || {
+        server.count_rows(Parameters(CountRowsParams {
+            table_name: "rmcp_test_transaction".to_string(),
+            where_conditions: None,
+            approximate: None,
+        }))
+    };
+
+    execute("CREATE TABLE IF NOT EXISTS rmcp_test_transaction (name text)")
+        .await
+        .unwrap();
+
+    let begun = server
+        .begin_transaction(Parameters(BeginTransactionParams { isolation_level: None }))
+        .await;
+    let nested = server
+        .begin_transaction(Parameters(BeginTransactionParams { isolation_level: None }))
+        .await;
+    let inserted = server
+        .insert_data(Parameters(InsertParams {
+            table_name: "rmcp_test_transaction".to_string(),
+            data: serde_json::json!({"name": "pending"}),
+            returning: None,
+            connection_override: None,
+        }))
+        .await;
+    let inside = count().await;
+    let rolled_back = server.rollback_transaction().await;
+    let after = count().await;
+    let commit_without_transaction = server.commit_transaction().await;
+
+    execute("DROP TABLE rmcp_test_transaction").await.unwrap();
+
+    assert_eq!(json_result(begun.unwrap())["status"], "open");
+    assert!(nested.is_err());
+    inserted.unwrap();
+    assert_eq!(json_result(inside.unwrap())["count"], 1);
+    assert_eq!(json_result(rolled_back.unwrap())["status"], "rolled_back");
+    assert_eq!(json_result(after.unwrap())["count"], 0);
+    assert!(commit_without_transaction.is_err());
+}
+
+#[tokio::test]
+async fn test_transaction_survives_failed_probes() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_probe_transaction").await.unwrap();
+    execute("CREATE TABLE rmcp_test_probe_transaction (name text)").await.unwrap();
+
+    server
+        .begin_transaction(Parameters(BeginTransactionParams { isolation_level: None }))
+        .await
+        .unwrap();
+    let inserted = execute("INSERT INTO rmcp_test_probe_transaction VALUES ('kept')").await;
+    // Before PostgreSQL 17 the pg_stat_checkpointer probe fails and falls back to pg_stat_bgwriter
+    let summary = server.get_activity_summary().await;
+    let after = execute("SELECT COUNT(*) AS n FROM rmcp_test_probe_transaction").await;
+    let committed = server.commit_transaction().await;
+    let persisted = execute("SELECT COUNT(*) AS n FROM rmcp_test_probe_transaction").await;
+
+    execute("DROP TABLE rmcp_test_probe_transaction").await.unwrap();
+
+    inserted.unwrap();
+    let summary = json_result(summary.unwrap());
+    assert!(summary["checkpoints"].is_object(), "{}", summary);
+    assert_eq!(json_result(after.unwrap())["rows"][0]["n"], 1);
+    assert_eq!(json_result(committed.unwrap())["status"], "committed");
+    assert_eq!(json_result(persisted.unwrap())["rows"][0]["n"], 1);
+}
+
+#[tokio::test]
+async fn test_query_data_caps_rows() {
+    let Some(server) = test_server() else { return };
+
+    let query = |max_rows: Option<u32>| {
+        server.query_data(Parameters(QueryParams {
+            query: "SELECT n FROM generate_series(1, 1500) AS n".to_string(),
+            max_rows,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+    };
+
+    let default_cap = json_result(query(None).await.unwrap());
+    assert_eq!(default_cap["row_count"], 1000);
+    assert_eq!(default_cap["truncated"], true);
+
+    let capped = json_result(query(Some(10)).await.unwrap());
+    assert_eq!(capped["row_count"], 10);
+    assert_eq!(capped["rows"][9]["n"], 10);
+    assert_eq!(capped["truncated"], true);
+
+    let complete = json_result(query(Some(1500)).await.unwrap());
+    assert_eq!(complete["row_count"], 1500);
+    assert!(complete.get("truncated").is_none());
+}
+
+#[tokio::test]
+async fn test_insert_data_binds_typed_values() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute(
+        "CREATE TABLE IF NOT EXISTS rmcp_test_insert
+             (age int, active bool, meta jsonb, price numeric, note text, born date)",
+    )
+    .await
+    .unwrap();
+
+    let inserted = server
+        .insert_data(Parameters(InsertParams {
+            table_name: "rmcp_test_insert".to_string(),
+            data: serde_json::json!({
+                "age": 30,
+                "active": true,
+                "meta": {"x": 1},
+                "price": 9.99,
+                "note": null,
+                "born": "1994-05-01"
+            }),
+            returning: None,
+            connection_override: None,
+        }))
+        .await;
+    let stored = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT age, active, meta, price, note, born,
+                           jsonb_typeof(meta) AS meta_type, note IS NULL AS note_is_null
+                    FROM rmcp_test_insert"
+                .to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await;
+
+    execute("DROP TABLE rmcp_test_insert").await.unwrap();
+
+    inserted.unwrap();
+    let row = &json_result(stored.unwrap())["rows"][0];
+    assert_eq!(row["age"], 30);
+    assert_eq!(row["active"], true);
+    assert_eq!(row["meta"], serde_json::json!({"x": 1}));
+    assert_eq!(row["meta_type"], "object");
+    assert_eq!(row["price"], "9.99");
+    assert_eq!(row["note"], serde_json::Value::Null);
+    assert_eq!(row["note_is_null"], true);
+    assert_eq!(row["born"], "1994-05-01");
+}
+
+#[tokio::test]
+async fn test_table_sample_pages() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute("CREATE TABLE IF NOT EXISTS rmcp_test_pages (id int PRIMARY KEY)")
+        .await
+        .unwrap();
+    execute("INSERT INTO rmcp_test_pages SELECT n FROM generate_series(1, 5) AS n ON CONFLICT DO NOTHING")
+        .await
+        .unwrap();
+
+    let page = |offset: Option<i64>, order_by: &str| {
+        server.get_table_sample(Parameters(TableSampleParams {
+            table_name: "rmcp_test_pages".to_string(),
+            limit: Some(2),
+            exclude_columns: None,
+            offset,
+            order_by: Some(order_by.to_string()),
+            where_conditions: None,
+        }))
+    };
+
+    let first = page(None, "id").await;
+    let last = page(Some(4), "id").await;
+    let unknown = page(None, "missing").await;
+
+    execute("DROP TABLE rmcp_test_pages").await.unwrap();
+
+    let first = json_result(first.unwrap());
+    assert_eq!(first["rows"], serde_json::json!([{"id": 1}, {"id": 2}]));
+    assert_eq!(first["has_more"], true);
+    let last = json_result(last.unwrap());
+    assert_eq!(last["rows"], serde_json::json!([{"id": 5}]));
+    assert_eq!(last["offset"], 4);
+    assert_eq!(last["has_more"], false);
+    assert!(unknown.is_err());
+}
+
+#[tokio::test]
+async fn test_get_table_sample_where_conditions() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let sample = |limit: Option<i32>, where_conditions: serde_json::Value| {
+        server.get_table_sample(Parameters(TableSampleParams {
+            table_name: "rmcp_test_sample_filter".to_string(),
+            limit,
+            exclude_columns: None,
+            offset: None,
+            order_by: Some("id".to_string()),
+            where_conditions: Some(where_conditions),
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_sample_filter").await.unwrap();
+    execute("CREATE TABLE rmcp_test_sample_filter (id int PRIMARY KEY, status text)").await.unwrap();
+    execute(
+        "INSERT INTO rmcp_test_sample_filter
+         SELECT n, CASE WHEN n % 2 = 0 THEN 'failed' WHEN n = 1 THEN NULL ELSE 'ok' END
+         FROM generate_series(1, 300) AS n",
+    )
+    .await
+    .unwrap();
+
+    let failed = sample(Some(2), serde_json::json!({"status": "failed"})).await;
+    let capped = sample(Some(1000), serde_json::json!({"status": "failed"})).await;
+    let missing = sample(None, serde_json::json!({"status": null})).await;
+    let injected = sample(None, serde_json::json!({"status": "x' OR '1'='1"})).await;
+
+    execute("DROP TABLE rmcp_test_sample_filter").await.unwrap();
+
+    let failed = json_result(failed.unwrap());
+    assert_eq!(
+        failed["rows"],
+        serde_json::json!([{"id": 2, "status": "failed"}, {"id": 4, "status": "failed"}])
+    );
+    assert_eq!(failed["has_more"], true);
+    let capped = json_result(capped.unwrap());
+    assert_eq!(capped["count"], 100);
+    assert_eq!(capped["has_more"], true);
+    assert_eq!(json_result(missing.unwrap())["rows"], serde_json::json!([{"id": 1, "status": null}]));
+    assert_eq!(json_result(injected.unwrap())["count"], 0);
+}
+
+#[tokio::test]
+async fn test_get_distinct_values() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let distinct = |column_name: &str, limit: Option<i64>| {
+        server.get_distinct_values(Parameters(DistinctValuesParams {
+            table_name: "rmcp_test_distinct".to_string(),
+            column_name: column_name.to_string(),
+            limit,
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_distinct").await.unwrap();
+    execute("CREATE TABLE rmcp_test_distinct (status text, \"Amount\" numeric)").await.unwrap();
+    execute(
+        "INSERT INTO rmcp_test_distinct VALUES
+         ('paid', 1.50), ('paid', 1.50), ('paid', 2), ('failed', 2), ('failed', NULL), (NULL, NULL), ('refunded', 3)",
+    )
+    .await
+    .unwrap();
+
+    let statuses = distinct("status", None).await;
+    let top = distinct("status", Some(1)).await;
+    let amounts = distinct("Amount", None).await;
+    let missing = distinct("missing", None).await;
+
+    execute("DROP TABLE rmcp_test_distinct").await.unwrap();
+
+    assert_eq!(
+        json_result(statuses.unwrap()),
+        serde_json::json!({
+            "table_name": "rmcp_test_distinct",
+            "column_name": "status",
+            "values": [
+                {"value": "paid", "count": 3},
+                {"value": "failed", "count": 2},
+                {"value": "refunded", "count": 1},
+                {"value": null, "count": 1},
+            ],
+            "distinct_count": 4,
+            "truncated": false,
+        })
+    );
+    let top = json_result(top.unwrap());
+    assert_eq!(top["values"], serde_json::json!([{"value": "paid", "count": 3}]));
+    assert_eq!(top["truncated"], true);
+    let amounts = json_result(amounts.unwrap());
+    assert_eq!(amounts["values"][0], serde_json::json!({"value": "1.50", "count": 2}));
+    assert!(missing.is_err());
+}
+
+#[tokio::test]
+async fn test_query_stream() {
+    let Some(server) = test_server() else { return };
+    let stream = |query: Option<&str>, cursor_id: Option<&str>, close: Option<bool>| {
+        server.query_stream(Parameters(QueryStreamParams {
+            query: query.map(str::to_string),
+            cursor_id: cursor_id.map(str::to_string),
+            batch_size: Some(10),
+            close,
+        }))
+    };
+
+    let first = json_result(
+        stream(Some("SELECT n FROM generate_series(1, 25) AS n ORDER BY n;"), None, None)
+            .await
+            .unwrap(),
+    );
+    assert_eq!(first["row_count"], 10);
+    assert_eq!(first["rows"][0]["n"], 1);
+    assert_eq!(first["has_more"], true);
+    let cursor_id = first["cursor_id"].as_str().unwrap().to_string();
+
+    let second = json_result(stream(None, Some(&cursor_id), None).await.unwrap());
+    assert_eq!(second["rows"][0]["n"], 11);
+    assert_eq!(second["cursor_id"], cursor_id.as_str());
+    let last = json_result(stream(None, Some(&cursor_id), None).await.unwrap());
+    assert_eq!(last["row_count"], 5);
+    assert_eq!(last["rows"][4]["n"], 25);
+    assert_eq!(last["cursor_id"], serde_json::Value::Null);
+    assert_eq!(last["has_more"], false);
+    assert!(stream(None, Some(&cursor_id), None).await.is_err());
+
+    let opened = json_result(stream(Some("SELECT generate_series(1, 100)"), None, None).await.unwrap());
+    let cursor_id = opened["cursor_id"].as_str().unwrap().to_string();
+    let closed = json_result(stream(None, Some(&cursor_id), Some(true)).await.unwrap());
+    assert_eq!(closed["closed"], true);
+    assert!(stream(None, Some(&cursor_id), None).await.is_err());
+
+    // The query is prepared, so a second statement after it is refused
+    let stacked = stream(Some("SELECT 1; CREATE TABLE rmcp_test_stream_stacked (id int)"), None, None).await;
+    assert!(stacked.is_err());
+    assert!(stream(None, None, None).await.is_err());
+}
+
+#[tokio::test]
+async fn test_query_stream_idle_cursor_is_closed() {
+    let Some(config) = std::env::var("POSTGRES_TEST_CONNECTION_STRING").ok() else { return };
+    let server = PostgresServer::new(config).with_cursor_idle_timeout(std::time::Duration::from_millis(200));
+
+    let opened = server
+        .query_stream(Parameters(QueryStreamParams {
+            query: Some("SELECT generate_series(1, 100)".to_string()),
+            cursor_id: None,
+            batch_size: Some(10),
+            close: None,
+        }))
+        .await;
+    let cursor_id = json_result(opened.unwrap())["cursor_id"].as_str().unwrap().to_string();
+    tokio::time::sleep(std::time::Duration::from_millis(600)).await;
+
+    let expired = server
+        .query_stream(Parameters(QueryStreamParams {
+            query: None,
+            cursor_id: Some(cursor_id),
+            batch_size: None,
+            close: None,
+        }))
+        .await;
+    assert!(expired.unwrap_err().message.contains("timed out"));
+}
+
+#[tokio::test]
+async fn test_insert_data_multiple_rows() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let insert = |data: serde_json::Value| {
+        server.insert_data(Parameters(InsertParams {
+            table_name: "rmcp_test_batch_insert".to_string(),
+            data,
+            returning: None,
+            connection_override: None,
+        }))
+    };
+
+    execute("CREATE TABLE IF NOT EXISTS rmcp_test_batch_insert (id int, label text)")
+        .await
+        .unwrap();
+
+    let inserted = insert(serde_json::json!([
+        {"id": 1, "label": "one"},
+        {"label": "two", "id": 2},
+        {"id": 3, "label": null}
+    ]))
+    .await;
+    let mismatched = insert(serde_json::json!([{"id": 4, "label": "four"}, {"id": 5}])).await;
+    let count = server
+        .count_rows(Parameters(CountRowsParams {
+            table_name: "rmcp_test_batch_insert".to_string(),
+            where_conditions: None,
+            approximate: None,
+        }))
+        .await;
+    let labels = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT id, label FROM rmcp_test_batch_insert ORDER BY id".to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await;
+
+    execute("DROP TABLE rmcp_test_batch_insert").await.unwrap();
+
+    assert_eq!(json_result(inserted.unwrap())["rows_affected"], 3);
+    assert!(mismatched.unwrap_err().message.contains("same keys"));
+    assert_eq!(json_result(count.unwrap())["count"], 3);
+    assert_eq!(
+        json_result(labels.unwrap())["rows"],
+        serde_json::json!([
+            {"id": 1, "label": "one"},
+            {"id": 2, "label": "two"},
+            {"id": 3, "label": null}
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_data_tools_returning() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_returning").await.unwrap();
+    execute("CREATE TABLE rmcp_test_returning (id serial PRIMARY KEY, label text)")
+        .await
+        .unwrap();
+
+    let inserted = server
+        .insert_data(Parameters(InsertParams {
+            table_name: "rmcp_test_returning".to_string(),
+            data: serde_json::json!([{"label": "first"}, {"label": "second"}]),
+            returning: Some(vec!["id".to_string()]),
+            connection_override: None,
+        }))
+        .await;
+    let updated = server
+        .update_data(Parameters(UpdateDataParams {
+            table_name: "rmcp_test_returning".to_string(),
+            values: serde_json::json!({"label": "renamed"}),
+            where_conditions: serde_json::json!({"id": 2}),
+            limit: None,
+            returning: Some(vec!["*".to_string()]),
+            connection_override: None,
+        }))
+        .await;
+    let deleted = server
+        .delete_data(Parameters(DeleteDataParams {
+            table_name: "rmcp_test_returning".to_string(),
+            where_conditions: serde_json::json!({"id": 1}),
+            limit: None,
+            returning: Some(vec!["label".to_string()]),
+            connection_override: None,
+        }))
+        .await;
+    let unknown = server
+        .delete_data(Parameters(DeleteDataParams {
+            table_name: "rmcp_test_returning".to_string(),
+            where_conditions: serde_json::json!({"id": 2}),
+            limit: None,
+            returning: Some(vec!["missing".to_string()]),
+            connection_override: None,
+        }))
+        .await;
+
+    execute("DROP TABLE rmcp_test_returning").await.unwrap();
+
+    let inserted = json_result(inserted.unwrap());
+    assert_eq!(inserted["rows_affected"], 2);
+    assert_eq!(inserted["rows"], serde_json::json!([{"id": 1}, {"id": 2}]));
+    assert_eq!(
+        json_result(updated.unwrap())["rows"],
+        serde_json::json!([{"id": 2, "label": "renamed"}])
+    );
+    assert_eq!(json_result(deleted.unwrap())["rows"], serde_json::json!([{"label": "first"}]));
+    assert!(unknown.unwrap_err().message.contains("missing"));
+}
+
+#[tokio::test]
+async fn test_statement_timeout_cancels_slow_queries() {
+    let Ok(connection_string) = std::env::var("POSTGRES_TEST_CONNECTION_STRING") else {
+        return;
+    };
+    let server = PostgresServer::new(connection_string)
+        .with_statement_timeout(std::time::Duration::from_millis(100));
+
+    let query = |query: &str| {
+        server.query_data(Parameters(QueryParams {
+            query: query.to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+    };
+
+    let started = std::time::Instant::now();
+    let err = query("SELECT pg_sleep(5)").await.unwrap_err();
+    assert!(started.elapsed() < std::time::Duration::from_secs(4));
+    assert!(err.message.contains("statement timeout"), "{}", err.message);
+
+    let fast = query("SELECT current_setting('statement_timeout') AS timeout").await.unwrap();
+    assert_eq!(json_result(fast)["rows"][0]["timeout"], "100ms");
+}
+
+#[tokio::test]
+async fn test_list_views() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute("CREATE OR REPLACE VIEW rmcp_test_view_b AS SELECT 1 AS one")
+        .await
+        .unwrap();
+    execute("CREATE MATERIALIZED VIEW IF NOT EXISTS rmcp_test_view_a AS SELECT 2 AS two")
+        .await
+        .unwrap();
+
+    let listed = server.list_views().await;
+
+    execute("DROP VIEW rmcp_test_view_b").await.unwrap();
+    execute("DROP MATERIALIZED VIEW rmcp_test_view_a").await.unwrap();
+
+    let listed = json_result(listed.unwrap());
+    let ours: Vec<&serde_json::Value> = listed["views"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|view| view["view_name"].as_str().unwrap().starts_with("rmcp_test_view_"))
+        .collect();
+    assert_eq!(ours.len(), 2);
+    assert_eq!(ours[0]["view_name"], "rmcp_test_view_a");
+    assert_eq!(ours[0]["kind"], "materialized_view");
+    assert_eq!(ours[1]["kind"], "view");
+    assert!(ours[1]["definition"].as_str().unwrap().contains("AS one"));
+}
+
+#[tokio::test]
+async fn test_schema_tools_accept_non_public_schema() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let schema = || Some("rmcp_test_analytics".to_string());
+
+    execute("DROP SCHEMA IF EXISTS rmcp_test_analytics CASCADE").await.unwrap();
+    execute("CREATE SCHEMA rmcp_test_analytics").await.unwrap();
+    execute("CREATE TABLE rmcp_test_analytics.users (id int PRIMARY KEY)")
+        .await
+        .unwrap();
+    execute(
+        "CREATE TABLE rmcp_test_analytics.rmcp_test_events
+             (id int PRIMARY KEY, user_id int REFERENCES rmcp_test_analytics.users (id))",
+    )
+    .await
+    .unwrap();
+
+    let tables = server
+        .list_tables(Parameters(ListTablesParams { schema: schema() }))
+        .await;
+    let exists = server
+        .table_exists(Parameters(TableNameParams {
+            table_name: "rmcp_test_events".to_string(),
+            schema: schema(),
+        }))
+        .await;
+    let missing_from_public = server
+        .table_exists(Parameters(TableNameParams {
+            table_name: "rmcp_test_events".to_string(),
+            schema: None,
+        }))
+        .await;
+    let described = server
+        .describe_table(Parameters(TableNameParams {
+            table_name: "rmcp_test_events".to_string(),
+            schema: schema(),
+        }))
+        .await;
+    let columns = server
+        .get_schema(Parameters(SchemaParams {
+            table_name: Some("rmcp_test_events".to_string()),
+            columns: None,
+            schema: schema(),
+        }))
+        .await;
+    let relationships = server
+        .get_relationships(Parameters(RelationshipsParams {
+            table_name: Some("rmcp_test_events".to_string()),
+            schema: schema(),
+        }))
+        .await;
+
+    execute("DROP SCHEMA rmcp_test_analytics CASCADE").await.unwrap();
+
+    assert_eq!(json_result(tables.unwrap())["tables"], serde_json::json!(["rmcp_test_events", "users"]));
+    assert_eq!(json_result(exists.unwrap())["exists"], true);
+    assert_eq!(json_result(missing_from_public.unwrap())["exists"], false);
+    let described = json_result(described.unwrap());
+    assert_eq!(described["columns"].as_array().unwrap().len(), 2);
+    assert_eq!(described["indexes"].as_array().unwrap().len(), 1);
+    assert_eq!(json_result(columns.unwrap()).as_array().unwrap().len(), 2);
+    let relationships = json_result(relationships.unwrap());
+    assert_eq!(relationships[0]["foreign_table_schema"], "rmcp_test_analytics");
+    assert_eq!(relationships[0]["foreign_table_name"], "users");
+}
+
+#[tokio::test]
+async fn test_get_primary_keys() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let primary_keys = |table: &str| {
+        server.get_primary_keys(Parameters(TableNameParams {
+            table_name: table.to_string(),
+            schema: None,
+        }))
+    };
+
+    execute(
+        "CREATE TABLE IF NOT EXISTS rmcp_test_composite_pk
+             (region text, seq int, note text, PRIMARY KEY (seq, region))",
+    )
+    .await
+    .unwrap();
+    execute("CREATE TABLE IF NOT EXISTS rmcp_test_no_pk (note text)")
+        .await
+        .unwrap();
+
+    let composite = primary_keys("rmcp_test_composite_pk").await;
+    let none = primary_keys("rmcp_test_no_pk").await;
+
+    execute("DROP TABLE rmcp_test_composite_pk, rmcp_test_no_pk").await.unwrap();
+
+    let composite = json_result(composite.unwrap());
+    assert_eq!(composite["primary_key"], serde_json::json!(["seq", "region"]));
+    assert_eq!(composite["constraint_name"], "rmcp_test_composite_pk_pkey");
+    let none = json_result(none.unwrap());
+    assert_eq!(none["primary_key"], serde_json::json!([]));
+    assert!(none["constraint_name"].is_null());
+}
+
+#[tokio::test]
+async fn test_get_table_size() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let table_size = |table: &str| {
+        server.get_table_size(Parameters(TableNameParams {
+            table_name: table.to_string(),
+            schema: None,
+        }))
+    };
+
+    execute("CREATE TABLE IF NOT EXISTS rmcp_test_size (id int PRIMARY KEY, body text)")
+        .await
+        .unwrap();
+    execute("INSERT INTO rmcp_test_size SELECT n, repeat('x', 100) FROM generate_series(1, 100) AS n ON CONFLICT DO NOTHING")
+        .await
+        .unwrap();
+
+    let sized = table_size("rmcp_test_size").await;
+    let missing = table_size("rmcp_test_no_such_table").await;
+
+    execute("DROP TABLE rmcp_test_size").await.unwrap();
+
+    let sized = json_result(sized.unwrap());
+    let bytes = |key: &str| sized[key]["bytes"].as_i64().unwrap();
+    assert!(bytes("table_size") > 0);
+    assert!(bytes("indexes_size") > 0);
+    assert!(bytes("total_size") >= bytes("table_size") + bytes("indexes_size") + bytes("toast_size"));
+    assert!(sized["total_size"]["pretty"].as_str().unwrap().ends_with("kB"));
+    assert!(missing.unwrap_err().message.contains("does not exist"));
+}
+
+#[tokio::test]
+async fn test_list_active_queries() {
+    let Some(server) = test_server() else { return };
+
+    let slow = server.query_data(Parameters(QueryParams {
+        query: "SELECT pg_sleep(1.5) AS rmcp_test_active".to_string(),
+        max_rows: None,
+        format: None,
+        include_types: None,
+        null_token: None,
+        connection_override: None,
+    }));
+    let listed = async {
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        let recent = server
+            .list_active_queries(Parameters(ActiveQueriesParams {
+                min_duration_ms: Some(200),
+            }))
+            .await;
+        let long = server
+            .list_active_queries(Parameters(ActiveQueriesParams {
+                min_duration_ms: Some(60_000),
+            }))
+            .await;
+        (recent, long)
+    };
+    let (slow, (recent, long)) = tokio::join!(slow, listed);
+
+    slow.unwrap();
+    let is_ours = |query: &serde_json::Value| {
+        query["query"]
+            .as_str()
+            .is_some_and(|text| text.contains("rmcp_test_active"))
+    };
+    let recent = json_result(recent.unwrap());
+    let ours: Vec<&serde_json::Value> = recent["queries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|q| is_ours(q))
+        .collect();
+    assert_eq!(ours.len(), 1);
+    assert_eq!(ours[0]["state"], "active");
+    assert!(ours[0]["duration_ms"].as_f64().unwrap() >= 200.0);
+    assert!(!json_result(long.unwrap())["queries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(is_ours));
+}
+
+#[tokio::test]
+async fn test_cancel_query() {
+    let Some(server) = test_server() else { return };
+
+    let slow = server.query_data(Parameters(QueryParams {
+        query: "SELECT pg_sleep(5) AS rmcp_test_cancel".to_string(),
+        max_rows: None,
+        format: None,
+        include_types: None,
+        null_token: None,
+        connection_override: None,
+    }));
+    let cancel = async {
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        let active = json_result(
+            server
+                .list_active_queries(Parameters(ActiveQueriesParams { min_duration_ms: None }))
+                .await
+                .unwrap(),
+        );
+        let pid = active["queries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|q| q["query"].as_str().is_some_and(|text| text.contains("rmcp_test_cancel")))
+            .expect("the sleeping query should be listed")["pid"]
+            .as_i64()
+            .unwrap() as i32;
+        server.cancel_query(Parameters(CancelQueryParams { pid, force: None })).await
+    };
+    let started = std::time::Instant::now();
+    let (slow, cancelled) = tokio::join!(slow, cancel);
+
+    let cancelled = json_result(cancelled.unwrap());
+    assert_eq!(cancelled["action"], "cancel");
+    assert_eq!(cancelled["signaled"], true);
+    assert!(slow.is_err());
+    assert!(started.elapsed() < std::time::Duration::from_secs(4));
+
+    let missing = server
+        .cancel_query(Parameters(CancelQueryParams { pid: i32::MAX, force: Some(true) }))
+        .await;
+    assert_eq!(json_result(missing.unwrap())["signaled"], false);
+}
+
+#[tokio::test]
+async fn test_run_maintenance() {
+    let Some(server) = test_server() else { return };
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let maintain = |table_name: &str, mode: &str| {
+        server.run_maintenance(Parameters(MaintenanceParams {
+            table_name: table_name.to_string(),
+            mode: mode.to_string(),
+            schema: None,
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_maintenance").await.unwrap();
+    execute("CREATE TABLE rmcp_test_maintenance (id int)").await.unwrap();
+    execute("INSERT INTO rmcp_test_maintenance SELECT generate_series(1, 100)").await.unwrap();
+
+    // VACUUM must not be sent down the open transaction's connection
+    server
+        .begin_transaction(Parameters(BeginTransactionParams { isolation_level: None }))
+        .await
+        .unwrap();
+    let vacuumed = maintain("rmcp_test_maintenance", "vacuum_analyze").await;
+    server.rollback_transaction().await.unwrap();
+    let analyzed = maintain("rmcp_test_maintenance", "analyze").await;
+    let full = maintain("rmcp_test_maintenance", "vacuum_full").await;
+    let unknown_mode = maintain("rmcp_test_maintenance", "reindex").await;
+    let missing = maintain("rmcp_test_maintenance_missing", "vacuum").await;
+
+    execute("DROP TABLE rmcp_test_maintenance").await.unwrap();
+
+    let vacuumed = json_result(vacuumed.unwrap());
+    assert_eq!(vacuumed["sql"], "VACUUM (ANALYZE) \"public\".\"rmcp_test_maintenance\"");
+    assert_eq!(vacuumed["estimated_rows"], 100);
+    assert_eq!(json_result(analyzed.unwrap())["estimated_rows"], 100);
+    assert!(json_result(full.unwrap()).get("estimated_rows").is_none());
+    assert!(unknown_mode.unwrap_err().message.contains("Unknown mode 'reindex'"));
+    assert!(missing.unwrap_err().message.contains("does not exist"));
+}
+
+#[tokio::test]
+async fn test_list_functions() {
+    let Some(server) = test_server() else { return };
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute("DROP FUNCTION IF EXISTS rmcp_test_fn_double(int)").await.unwrap();
+    execute("DROP PROCEDURE IF EXISTS rmcp_test_fn_noop(text)").await.unwrap();
+    execute("CREATE FUNCTION rmcp_test_fn_double(x int) RETURNS int IMMUTABLE LANGUAGE sql AS 'SELECT x * 2'")
+        .await
+        .unwrap();
+    execute("CREATE PROCEDURE rmcp_test_fn_noop(label text DEFAULT 'x') LANGUAGE plpgsql AS 'BEGIN END'")
+        .await
+        .unwrap();
+
+    let listed = server
+        .list_functions(Parameters(ListFunctionsParams {
+            name_pattern: Some("RMCP_TEST_FN".to_string()),
+            schema: None,
+        }))
+        .await;
+    let unmatched = server
+        .list_functions(Parameters(ListFunctionsParams {
+            name_pattern: Some("rmcp_test_fn_missing".to_string()),
+            schema: None,
+        }))
+        .await;
+
+    execute("DROP FUNCTION rmcp_test_fn_double(int)").await.unwrap();
+    execute("DROP PROCEDURE rmcp_test_fn_noop(text)").await.unwrap();
+
+    let listed = json_result(listed.unwrap());
+    assert_eq!(
+        listed["functions"][0],
+        serde_json::json!({
+            "name": "rmcp_test_fn_double",
+            "kind": "function",
+            "arguments": "x integer",
+            "return_type": "integer",
+            "language": "sql",
+            "volatility": "immutable",
+        })
+    );
+    let procedure = &listed["functions"][1];
+    assert_eq!(procedure["name"], "rmcp_test_fn_noop");
+    assert_eq!(procedure["kind"], "procedure");
+    assert_eq!(procedure["return_type"], serde_json::Value::Null);
+    assert_eq!(procedure["language"], "plpgsql");
+    // Newer servers prefix procedure arguments with their mode (IN)
+    assert!(procedure["arguments"].as_str().unwrap().ends_with("label text DEFAULT 'x'::text"));
+    assert_eq!(listed["count"], 2);
+    assert_eq!(json_result(unmatched.unwrap())["count"], 0);
+}
+
+#[tokio::test]
+async fn test_ping() {
+    let Some(server) = test_server() else { return };
+
+    let pong = json_result(server.ping().await.unwrap());
+    assert_eq!(pong["ok"], true);
+    assert!(pong["latency_ms"].is_u64());
+
+    let unreachable = PostgresServer::new("host=127.0.0.1 port=1 user=postgres connect_timeout=1");
+    let err = unreachable.ping().await.unwrap_err();
+    assert!(err.message.starts_with("Ping failed: "), "{}", err.message);
+}
+
+#[tokio::test]
+async fn test_search_columns() {
+    let Some(server) = test_server() else { return };
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let search = |pattern: &str, exact: Option<bool>| {
+        server.search_columns(Parameters(SearchColumnsParams {
+            pattern: pattern.to_string(),
+            exact,
+            schema: None,
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_search_a, rmcp_test_search_b").await.unwrap();
+    execute("CREATE TABLE rmcp_test_search_b (id serial, work_email text, emailxverified boolean)")
+        .await
+        .unwrap();
+    execute("CREATE TABLE rmcp_test_search_a (id serial, email varchar(200), email_verified boolean)")
+        .await
+        .unwrap();
+    let substring = search("EMAIL", None).await;
+    let wildcard = search("email*", None).await;
+    let exact = search("email_verified", Some(true)).await;
+    execute("DROP TABLE rmcp_test_search_a, rmcp_test_search_b").await.unwrap();
+
+    let ours = |result: &serde_json::Value| -> Vec<(String, String)> {
+        result["columns"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|c| c["table_name"].as_str().unwrap().starts_with("rmcp_test_search_"))
+            .map(|c| (c["table_name"].as_str().unwrap().to_string(), c["column_name"].as_str().unwrap().to_string()))
+            .collect()
+    };
+    let substring = json_result(substring.unwrap());
+    assert_eq!(
+        ours(&substring),
+        vec![
+            ("rmcp_test_search_a".to_string(), "email".to_string()),
+            ("rmcp_test_search_a".to_string(), "email_verified".to_string()),
+            ("rmcp_test_search_b".to_string(), "work_email".to_string()),
+            ("rmcp_test_search_b".to_string(), "emailxverified".to_string()),
+        ]
+    );
+    let email = substring["columns"].as_array().unwrap().iter().find(|c| c["table_name"] == "rmcp_test_search_a").unwrap();
+    assert_eq!(email["data_type"], "character varying");
+    assert_eq!(ours(&json_result(wildcard.unwrap())).len(), 3);
+    assert_eq!(
+        ours(&json_result(exact.unwrap())),
+        vec![("rmcp_test_search_a".to_string(), "email_verified".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn test_query_data_csv_format() {
+    let Some(server) = test_server() else { return };
+    let query = |sql: &str, max_rows: Option<u32>| {
+        server.query_data(Parameters(QueryParams {
+            query: sql.to_string(),
+            max_rows,
+            format: Some("csv".to_string()),
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+    };
+
+    let quoted = query(
+        "SELECT * FROM (VALUES (1, 'plain', true), (2, 'Smith, \"Jr\"', NULL), (3, '\\N', false), (4, '', false))
+         AS t(id, name, active) ORDER BY id",
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(quoted.content.len(), 1);
+    assert_eq!(
+        quoted.content[0].as_text().unwrap().text,
+        "id,name,active\n1,plain,true\n2,\"Smith, \"\"Jr\"\"\",\\N\n3,\"\\N\",false\n4,,false\n"
+    );
+
+    let empty = query("SELECT 1 AS id, 'x' AS name WHERE false", None).await.unwrap();
+    assert_eq!(empty.content[0].as_text().unwrap().text, "id,name\n");
+
+    let truncated = query("SELECT generate_series(1, 5) AS n", Some(2)).await.unwrap();
+    assert_eq!(truncated.content[0].as_text().unwrap().text, "n\n1\n2\n");
+    let notice: serde_json::Value = serde_json::from_str(&truncated.content[1].as_text().unwrap().text).unwrap();
+    assert_eq!(notice["truncated"], true);
+    assert_eq!(notice["row_count"], 2);
+
+    let unknown = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT 1".to_string(),
+            max_rows: None,
+            format: Some("xml".to_string()),
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await;
+    assert!(unknown.unwrap_err().message.contains("Unknown format"));
+}
+
+#[tokio::test]
+async fn test_query_data_null_token() {
+    let Some(server) = test_server() else { return };
+    let query = |format: &str, null_token: Option<&str>| {
+        server.query_data(Parameters(QueryParams {
+            query: "SELECT * FROM (VALUES (1, 'NULL'), (2, NULL), (3, '')) AS t(id, name) ORDER BY id".to_string(),
+            max_rows: None,
+            format: Some(format.to_string()),
+            include_types: None,
+            null_token: null_token.map(str::to_string),
+            connection_override: None,
+        }))
+    };
+    let text = |result: CallToolResult| result.content[0].as_text().unwrap().text.clone();
+
+    assert_eq!(
+        text(query("markdown", None).await.unwrap()),
+        "| id | name |\n| --- | --- |\n| 1 | `NULL` |\n| 2 | NULL |\n| 3 |  |\n"
+    );
+    assert_eq!(text(query("csv", Some("NULL")).await.unwrap()), "id,name\n1,\"NULL\"\n2,NULL\n3,\n");
+    assert_eq!(text(query("csv", Some("")).await.unwrap()), "id,name\n1,NULL\n2,\n3,\"\"\n");
+}
+
+#[tokio::test]
+async fn test_query_preview() {
+    let Some(server) = test_server() else { return };
+
+    let preview = json_result(
+        server
+            .query_preview(Parameters(QueryPreviewParams {
+                query: "SELECT n, n::text AS label FROM generate_series(1, 500) AS n;".to_string(),
+                connection_override: None,
+            }))
+            .await
+            .unwrap(),
+    );
+    assert_eq!(preview["columns"][0]["type"], "int4");
+    assert_eq!(preview["columns"][1]["type"], "text");
+    assert_eq!(preview["first_row"]["label"], "1");
+    assert!(preview["estimated_total_rows"].as_i64().unwrap() > 1);
+}
+
+#[tokio::test]
+async fn test_money_and_interval_columns() {
+    let Some(server) = test_server() else { return };
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_money_interval").await.unwrap();
+    execute("CREATE TABLE rmcp_test_money_interval (id int, price money, duration interval)").await.unwrap();
+    execute(
+        "INSERT INTO rmcp_test_money_interval VALUES
+         (1, '$1,234.56'::money, '2 days 3 hours'::interval),
+         (2, NULL, NULL)",
+    )
+    .await
+    .unwrap();
+
+    let result = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT price, duration FROM rmcp_test_money_interval ORDER BY id".to_string(),
+            max_rows: None,
+            format: None,
+            include_types: Some(true),
+            null_token: None,
+            connection_override: None,
+        }))
+        .await;
+
+    execute("DROP TABLE rmcp_test_money_interval").await.unwrap();
+
+    let json = json_result(result.unwrap());
+    assert_eq!(
+        json["rows"],
+        serde_json::json!([
+            {"price": "1234.56", "duration": "P2DT3H"},
+            {"price": null, "duration": null},
+        ])
+    );
+    assert_eq!(json["columns"][0]["decoded"], true);
+    assert_eq!(json["columns"][1]["decoded"], true);
+}
+
+#[tokio::test]
+async fn test_network_address_columns() {
+    let Some(server) = test_server() else { return };
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_network").await.unwrap();
+    execute("CREATE TABLE rmcp_test_network (id int, client inet, subnet cidr, device macaddr)").await.unwrap();
+    execute(
+        "INSERT INTO rmcp_test_network VALUES
+         (1, '192.168.1.1', '192.168.1.0/24'::cidr, '08:00:2b:01:02:03'),
+         (2, '2001:db8::1/64', '2001:db8::/32', NULL),
+         (3, NULL, NULL, NULL)",
+    )
+    .await
+    .unwrap();
+
+    let result = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT client, subnet, device, network('192.168.1.1/24'::inet) AS network
+                    FROM rmcp_test_network ORDER BY id"
+                .to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await;
+    let invalid_cidr = execute("SELECT '192.168.1.1/24'::cidr").await;
+
+    execute("DROP TABLE rmcp_test_network").await.unwrap();
+
+    let json = json_result(result.unwrap());
+    assert_eq!(json["rows"][0]["client"], "192.168.1.1");
+    assert_eq!(json["rows"][0]["subnet"], "192.168.1.0/24");
+    assert_eq!(json["rows"][0]["device"], "08:00:2b:01:02:03");
+    assert_eq!(json["rows"][0]["network"], "192.168.1.0/24");
+    assert_eq!(json["rows"][1]["client"], "2001:db8::1/64");
+    assert_eq!(json["rows"][1]["subnet"], "2001:db8::/32");
+    assert!(json["rows"][2]["client"].is_null());
+    assert!(json["rows"][2]["subnet"].is_null());
+    assert!(json["rows"][2]["device"].is_null());
+    // cidr rejects host bits, so the request's example only exists as an inet
+    assert!(invalid_cidr.is_err());
+}
+
+#[tokio::test]
+async fn test_undecodable_values_are_not_null() {
+    let Some(server) = test_server() else { return };
+
+    let result = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT '<a>1</a>'::xml AS doc,
+                           NULL::xml AS no_doc,
+                           '(1,2)'::point AS location"
+                .to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await;
+
+    let json = json_result(result.unwrap());
+    let row = &json["rows"][0];
+    // xml has no decoder but a text wire format, so its raw value is the text
+    assert_eq!(row["doc"], "<a>1</a>");
+    assert!(row["no_doc"].is_null());
+    // point has no decoder and a binary wire format (two float8s), so it comes back as hex
+    assert_eq!(row["location"], "\\x3ff00000000000004000000000000000");
+}
+
+#[tokio::test]
+async fn test_allowed_tables() {
+    let Some(server) = test_server() else { return };
+    let Some(restricted) = test_server() else { return };
+    let restricted = restricted.with_allowed_tables(["rmcp_test_allowed"]);
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let query = |sql: &str| {
+        restricted.query_data(Parameters(QueryParams {
+            query: sql.to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_allowed, rmcp_test_secret, rmcp_test_secret_child CASCADE")
+        .await
+        .unwrap();
+    execute("CREATE TABLE rmcp_test_allowed (id int PRIMARY KEY, note text)").await.unwrap();
+    execute("CREATE TABLE rmcp_test_secret (id int REFERENCES rmcp_test_allowed, token text)").await.unwrap();
+    execute("CREATE INDEX rmcp_test_secret_token ON rmcp_test_secret (token)").await.unwrap();
+    execute("CREATE TABLE rmcp_test_secret_child () INHERITS (rmcp_test_allowed)").await.unwrap();
+    let inserted = restricted
+        .insert_data(Parameters(InsertParams {
+            table_name: "rmcp_test_allowed".to_string(),
+            data: serde_json::json!({"id": 1, "note": "rmcp_test_secret"}),
+            returning: None,
+            connection_override: None,
+        }))
+        .await;
+    let allowed = query("SELECT note FROM rmcp_test_allowed WHERE note = 'rmcp_test_secret'").await;
+    let catalog = query("SELECT relname FROM pg_class WHERE relname = 'rmcp_test_allowed'").await;
+    let joined = query("SELECT * FROM rmcp_test_allowed a JOIN public.rmcp_test_secret s USING (id)").await;
+    let quoted = query("WITH t AS (SELECT * FROM \"rmcp_test_secret\") SELECT * FROM t").await;
+    let raw = restricted
+        .execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: "DELETE FROM rmcp_test_secret".to_string(),
+            params: None,
+            connection_override: None,
+        }))
+        .await;
+    let counted = restricted
+        .count_rows(Parameters(CountRowsParams {
+            table_name: "rmcp_test_secret".to_string(),
+            where_conditions: None,
+            approximate: None,
+        }))
+        .await;
+    let described = restricted
+        .describe_table(Parameters(TableNameParams {
+            table_name: "rmcp_test_secret".to_string(),
+            schema: None,
+        }))
+        .await;
+    let listed = restricted.list_tables(Parameters(ListTablesParams { schema: None })).await;
+    let relationships = restricted
+        .get_relationships(Parameters(RelationshipsParams {
+            table_name: None,
+            schema: None,
+        }))
+        .await;
+    let inheritance = restricted
+        .get_inheritance(Parameters(TableNameParams {
+            table_name: "rmcp_test_allowed".to_string(),
+            schema: None,
+        }))
+        .await;
+    let io_stats = restricted
+        .get_io_stats(Parameters(IoStatsParams {
+            table_name: None,
+            limit: Some(10000),
+        }))
+        .await;
+    let unused_indexes = restricted
+        .find_unused_indexes(Parameters(UnusedIndexesParams {
+            min_size_bytes: None,
+            table_name: None,
+        }))
+        .await;
+    execute("DROP TABLE rmcp_test_allowed, rmcp_test_secret, rmcp_test_secret_child").await.unwrap();
+
+    inserted.unwrap();
+    assert_eq!(json_result(allowed.unwrap())["rows"][0]["note"], "rmcp_test_secret");
+    assert_eq!(json_result(catalog.unwrap())["row_count"], 1);
+    for result in [joined, quoted, raw, counted, described] {
+        let err = result.expect_err("the table allowlist should reject the call");
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_REQUEST);
+        assert!(err.message.contains("public.rmcp_test_secret"), "{}", err.message);
+    }
+    assert_eq!(json_result(listed.unwrap())["tables"], serde_json::json!(["rmcp_test_allowed"]));
+    for result in [relationships, inheritance, io_stats, unused_indexes] {
+        let text = result.unwrap().content[0].as_text().unwrap().text.clone();
+        assert!(!text.contains("rmcp_test_secret"), "{}", text);
+    }
+}
+
+#[tokio::test]
+async fn test_table_comments() {
+    let Some(server) = test_server() else { return };
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let table = |table_name: &str| {
+        Parameters(TableNameParams {
+            table_name: table_name.to_string(),
+            schema: None,
+        })
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_comments").await.unwrap();
+    execute("CREATE TABLE rmcp_test_comments (id int, dropped int, amount int)").await.unwrap();
+    execute("ALTER TABLE rmcp_test_comments DROP COLUMN dropped").await.unwrap();
+    execute("COMMENT ON TABLE rmcp_test_comments IS 'One row per payment'").await.unwrap();
+    execute("COMMENT ON COLUMN rmcp_test_comments.amount IS 'In cents'").await.unwrap();
+    let comments = server.get_table_comments(table("rmcp_test_comments")).await;
+    let described = server.describe_table(table("rmcp_test_comments")).await;
+    let missing = server.get_table_comments(table("rmcp_test_comments_missing")).await;
+    execute("DROP TABLE rmcp_test_comments").await.unwrap();
+
+    assert_eq!(
+        json_result(comments.unwrap()),
+        serde_json::json!({
+            "schema": "public",
+            "table_name": "rmcp_test_comments",
+            "comment": "One row per payment",
+            "columns": [
+                {"column_name": "id", "comment": null},
+                {"column_name": "amount", "comment": "In cents"},
+            ],
+        })
+    );
+    let described = json_result(described.unwrap());
+    assert_eq!(described["comment"], "One row per payment");
+    assert_eq!(described["columns"][0]["comment"], serde_json::Value::Null);
+    assert_eq!(described["columns"][1]["comment"], "In cents");
+    assert!(missing.unwrap_err().message.contains("does not exist"));
+}
+
+#[tokio::test]
+async fn test_get_sequences() {
+    let Some(server) = test_server() else { return };
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let sequences = |table_name: Option<&str>| {
+        server.get_sequences(Parameters(SequencesParams {
+            table_name: table_name.map(str::to_string),
+            schema: None,
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_seq_owner").await.unwrap();
+    execute("DROP SEQUENCE IF EXISTS rmcp_test_seq_small").await.unwrap();
+    execute(
+        "CREATE TABLE rmcp_test_seq_owner (id serial, code int GENERATED ALWAYS AS IDENTITY (START 10 INCREMENT 5))",
+    )
+    .await
+    .unwrap();
+    execute("INSERT INTO rmcp_test_seq_owner DEFAULT VALUES").await.unwrap();
+    execute("CREATE SEQUENCE rmcp_test_seq_small AS smallint MINVALUE 1 MAXVALUE 200").await.unwrap();
+    execute("SELECT setval('rmcp_test_seq_small', 150)").await.unwrap();
+
+    let owned = sequences(Some("rmcp_test_seq_owner")).await;
+    let all = sequences(None).await;
+    let after = execute("SELECT last_value FROM rmcp_test_seq_owner_id_seq").await;
+
+    execute("DROP TABLE rmcp_test_seq_owner").await.unwrap();
+    execute("DROP SEQUENCE rmcp_test_seq_small").await.unwrap();
+
+    let owned = json_result(owned.unwrap());
+    assert_eq!(owned["count"], 2);
+    assert_eq!(owned["sequences"][0]["sequence_name"], "rmcp_test_seq_owner_code_seq");
+    assert_eq!(owned["sequences"][0]["last_value"], 10);
+    assert_eq!(owned["sequences"][0]["increment_by"], 5);
+    assert_eq!(
+        owned["sequences"][0]["owned_by"],
+        serde_json::json!({"table_name": "rmcp_test_seq_owner", "column_name": "code", "identity": true})
+    );
+    assert_eq!(owned["sequences"][1]["sequence_name"], "rmcp_test_seq_owner_id_seq");
+    assert_eq!(owned["sequences"][1]["owned_by"]["identity"], false);
+    // Listing reads the sequence without consuming a value
+    assert_eq!(json_result(after.unwrap())["rows"][0]["last_value"], 1);
+
+    let all = json_result(all.unwrap());
+    let small = all["sequences"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|sequence| sequence["sequence_name"] == "rmcp_test_seq_small")
+        .unwrap();
+    assert_eq!(small["data_type"], "smallint");
+    assert_eq!(small["owned_by"], serde_json::Value::Null);
+    assert_eq!(small["used_percent"], 74.87);
+}
+
+#[tokio::test]
+async fn test_describe_table_constraints() {
+    let Some(server) = test_server() else { return };
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_constraints").await.unwrap();
+    execute(
+        "CREATE TABLE rmcp_test_constraints (
+             id int PRIMARY KEY,
+             sku text NOT NULL,
+             region text,
+             qty int CONSTRAINT qty_positive CHECK (qty > 0),
+             CONSTRAINT sku_per_region UNIQUE (region, sku)
+         )",
+    )
+    .await
+    .unwrap();
+
+    let described = server
+        .describe_table(Parameters(TableNameParams {
+            table_name: "rmcp_test_constraints".to_string(),
+            schema: None,
+        }))
+        .await;
+
+    execute("DROP TABLE rmcp_test_constraints").await.unwrap();
+
+    assert_eq!(
+        json_result(described.unwrap())["constraints"],
+        serde_json::json!([
+            {
+                "constraint_name": "rmcp_test_constraints_pkey",
+                "constraint_type": "PRIMARY KEY",
+                "columns": ["id"],
+                "definition": "PRIMARY KEY (id)",
+            },
+            {
+                "constraint_name": "sku_per_region",
+                "constraint_type": "UNIQUE",
+                "columns": ["region", "sku"],
+                "definition": "UNIQUE (region, sku)",
+            },
+            {
+                "constraint_name": "qty_positive",
+                "constraint_type": "CHECK",
+                "columns": ["qty"],
+                "definition": "CHECK ((qty > 0))",
+            },
+            {"constraint_name": null, "constraint_type": "NOT NULL", "columns": ["id"], "definition": "NOT NULL"},
+            {"constraint_name": null, "constraint_type": "NOT NULL", "columns": ["sku"], "definition": "NOT NULL"},
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_analyze_database() {
+    let Some(server) = test_server() else { return };
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_overview_big, rmcp_test_overview_small").await.unwrap();
+    execute("CREATE TABLE rmcp_test_overview_big (id int PRIMARY KEY, body text)").await.unwrap();
+    execute("CREATE INDEX ON rmcp_test_overview_big (body)").await.unwrap();
+    execute("INSERT INTO rmcp_test_overview_big SELECT g, repeat('x', 200) FROM generate_series(1, 2000) g")
+        .await
+        .unwrap();
+    execute("ANALYZE rmcp_test_overview_big").await.unwrap();
+    execute("CREATE TABLE rmcp_test_overview_small (id int)").await.unwrap();
+    let result = server.analyze_database(Parameters(ListTablesParams { schema: None })).await;
+    execute("DROP TABLE rmcp_test_overview_big, rmcp_test_overview_small").await.unwrap();
+
+    let json = json_result(result.unwrap());
+    let tables = json["tables"].as_array().unwrap();
+    let position = |name: &str| tables.iter().position(|t| t["table_name"] == name).unwrap();
+    let (big, small) = (&tables[position("rmcp_test_overview_big")], &tables[position("rmcp_test_overview_small")]);
+    assert!(position("rmcp_test_overview_big") < position("rmcp_test_overview_small"));
+    assert_eq!(big["estimated_rows"], 2000);
+    assert_eq!(big["index_count"], 2);
+    assert!(big["last_analyze"].is_string());
+    assert!(small["estimated_rows"].is_null());
+    assert_eq!(small["index_count"], 0);
+    assert!(small["last_vacuum"].is_null());
+    let sizes: Vec<i64> = tables.iter().map(|t| t["total_size"]["bytes"].as_i64().unwrap()).collect();
+    assert!(sizes.windows(2).all(|pair| pair[0] >= pair[1]));
+}
+
+#[tokio::test]
+async fn test_execute_raw_query_binds_params() {
+    let Some(server) = test_server() else { return };
+    let execute = |query: &str, params: Option<Vec<serde_json::Value>>| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params,
+            connection_override: None,
+        }))
+    };
+
+    let sum = execute("SELECT $1::int + $2::int AS total", Some(vec![serde_json::json!(2), serde_json::json!(3)])).await;
+    assert_eq!(json_result(sum.unwrap())["rows"][0]["total"], 5);
+
+    execute("DROP TABLE IF EXISTS rmcp_test_raw_params", None).await.unwrap();
+    execute("CREATE TABLE rmcp_test_raw_params (id int, label text, active boolean, meta jsonb)", None)
+        .await
+        .unwrap();
+    let inserted = execute(
+        "INSERT INTO rmcp_test_raw_params VALUES ($1, $2, $3, $4), ($5, $6, $7, $8)",
+        Some(vec![
+            serde_json::json!(1),
+            serde_json::json!("it's"),
+            serde_json::json!(true),
+            serde_json::json!({"k": [1, 2]}),
+            serde_json::json!(2),
+            serde_json::json!(null),
+            serde_json::json!(false),
+            serde_json::json!(null),
+        ]),
+    )
+    .await;
+    let selected = execute(
+        "SELECT id, label, meta FROM rmcp_test_raw_params WHERE active = $1",
+        Some(vec![serde_json::json!(true)]),
+    )
+    .await;
+    let mismatched = execute("SELECT $1::int", Some(vec![])).await;
+    execute("DROP TABLE rmcp_test_raw_params", None).await.unwrap();
+
+    assert_eq!(json_result(inserted.unwrap())["rows_affected"], 2);
+    assert_eq!(
+        json_result(selected.unwrap())["rows"],
+        serde_json::json!([{"id": 1, "label": "it's", "meta": {"k": [1, 2]}}])
+    );
+    assert!(mismatched.unwrap_err().message.contains("expects 1"));
+}
+
+#[tokio::test]
+async fn test_execute_raw_query_returns_rows_for_any_row_returning_statement() {
+    let Some(server) = test_server() else { return };
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    let cte = execute("WITH nums AS (SELECT generate_series(1, 3) AS n) SELECT sum(n) AS total FROM nums").await;
+    let commented = execute("-- how many?\n/* a block comment */\n  SELECT 42 AS answer").await;
+    let shown = execute("SHOW standard_conforming_strings").await;
+
+    execute("DROP TABLE IF EXISTS rmcp_test_raw_returning").await.unwrap();
+    execute("CREATE TABLE rmcp_test_raw_returning (id serial, label text)").await.unwrap();
+    let returned = execute("INSERT INTO rmcp_test_raw_returning (label) VALUES ('a'), ('b') RETURNING label").await;
+    let updated = execute("UPDATE rmcp_test_raw_returning SET label = 'c'").await;
+    execute("DROP TABLE rmcp_test_raw_returning").await.unwrap();
+
+    assert_eq!(json_result(cte.unwrap())["rows"][0]["total"], 6);
+    assert_eq!(json_result(commented.unwrap())["rows"][0]["answer"], 42);
+    assert_eq!(json_result(shown.unwrap())["rows"][0]["standard_conforming_strings"], "on");
+    let returned = json_result(returned.unwrap());
+    assert_eq!(returned["rows"], serde_json::json!([{"label": "a"}, {"label": "b"}]));
+    assert_eq!(returned["count"], 2);
+    assert_eq!(json_result(updated.unwrap())["rows_affected"], 2);
+}
+
+#[tokio::test]
+async fn test_enum_values_and_columns() {
+    let Some(server) = test_server() else { return };
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let enum_values = |type_name: Option<&str>, table_name: Option<&str>, column_name: Option<&str>| {
+        server.get_enum_values(Parameters(EnumValuesParams {
+            type_name: type_name.map(str::to_string),
+            table_name: table_name.map(str::to_string),
+            column_name: column_name.map(str::to_string),
+            schema: None,
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_tickets").await.unwrap();
+    execute("DROP TYPE IF EXISTS rmcp_test_status").await.unwrap();
+    execute("CREATE TYPE rmcp_test_status AS ENUM ('open', 'in_progress', 'closed')").await.unwrap();
+    execute("ALTER TYPE rmcp_test_status ADD VALUE 'blocked' BEFORE 'closed'").await.unwrap();
+    execute("CREATE TABLE rmcp_test_tickets (id int, status rmcp_test_status, history rmcp_test_status[])")
+        .await
+        .unwrap();
+    execute("INSERT INTO rmcp_test_tickets VALUES (1, 'blocked', '{open,blocked}'), (2, NULL, NULL)")
+        .await
+        .unwrap();
+    let rows = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT status, history FROM rmcp_test_tickets ORDER BY id".to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await;
+    let by_type = enum_values(Some("rmcp_test_status"), None, None).await;
+    let by_column = enum_values(None, Some("rmcp_test_tickets"), Some("history")).await;
+    let not_enum = enum_values(None, Some("rmcp_test_tickets"), Some("id")).await;
+    let missing = enum_values(Some("rmcp_test_no_such_type"), None, None).await;
+    execute("DROP TABLE rmcp_test_tickets").await.unwrap();
+    execute("DROP TYPE rmcp_test_status").await.unwrap();
+
+    assert_eq!(
+        json_result(rows.unwrap())["rows"],
+        serde_json::json!([
+            {"status": "blocked", "history": ["open", "blocked"]},
+            {"status": null, "history": null}
+        ])
+    );
+    let expected = serde_json::json!(["open", "in_progress", "blocked", "closed"]);
+    assert_eq!(json_result(by_type.unwrap())["values"], expected);
+    let by_column = json_result(by_column.unwrap());
+    assert_eq!(by_column["type_name"], "rmcp_test_status");
+    assert_eq!(by_column["values"], expected);
+    assert!(not_enum.unwrap_err().message.contains("not an enum"));
+    assert!(missing.unwrap_err().message.contains("does not exist"));
+}
+
+#[tokio::test]
+async fn test_query_data_includes_column_types() {
+    let Some(server) = test_server() else { return };
+    let query = |sql: &str, include_types: Option<bool>| {
+        server.query_data(Parameters(QueryParams {
+            query: sql.to_string(),
+            max_rows: None,
+            format: None,
+            include_types,
+            null_token: None,
+            connection_override: None,
+        }))
+    };
+
+    let typed = query(
+        "SELECT '2024-01-01'::date AS created_at, 'x'::text AS created_at, '(1,2)'::point AS location",
+        Some(true),
+    )
+    .await;
+    let empty = query("SELECT 1::bigint AS n WHERE false", Some(true)).await;
+    let untyped = query("SELECT 1 AS n", None).await;
+
+    assert_eq!(
+        json_result(typed.unwrap())["columns"],
+        serde_json::json!([
+            {"name": "created_at", "type": "date", "decoded": true},
+            {"name": "created_at_2", "type": "text", "decoded": true},
+            {"name": "location", "type": "point", "decoded": false}
+        ])
+    );
+    assert_eq!(
+        json_result(empty.unwrap())["columns"],
+        serde_json::json!([{"name": "n", "type": "int8", "decoded": true}])
+    );
+    assert!(json_result(untyped.unwrap()).get("columns").is_none());
+}
+
+/// Provider whose first connection has already been terminated by the server
+struct DroppedFirstConnection {
+    config: String,
+    calls: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+#[async_trait::async_trait]
+impl ClientProvider for DroppedFirstConnection {
+    async fn get_client(&self) -> anyhow::Result<ProvidedClient> {
+        let (client, connection) = tokio_postgres::connect(&self.config, tokio_postgres::NoTls).await?;
+        tokio::spawn(connection);
+        if self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+            let (killer, connection) = tokio_postgres::connect(&self.config, tokio_postgres::NoTls).await?;
+            tokio::spawn(connection);
+            let pid: i32 = client.query_one("SELECT pg_backend_pid()", &[]).await?.get(0);
+            killer.execute("SELECT pg_terminate_backend($1)", &[&pid]).await?;
+        }
+        Ok(client.into())
+    }
+}
+
+#[tokio::test]
+async fn test_reconnects_after_dropped_connection() {
+    let Some(config) = std::env::var("POSTGRES_TEST_CONNECTION_STRING").ok() else { return };
+    let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let server = PostgresServer::from_provider(DroppedFirstConnection {
+        config,
+        calls: calls.clone(),
+    });
+
+    let result = server
+        .query_data(Parameters(QueryParams {
+            query: "SELECT 1 AS one".to_string(),
+            max_rows: None,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+        .await;
+
+    assert_eq!(json_result(result.unwrap())["rows"][0]["one"], 1);
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_max_result_rows() {
+    let Some(server) = test_server().map(|server| server.with_max_result_rows(5)) else { return };
+
+    let query = |max_rows: Option<u32>| {
+        server.query_data(Parameters(QueryParams {
+            query: "SELECT n FROM generate_series(1, 20) AS n".to_string(),
+            max_rows,
+            format: None,
+            include_types: None,
+            null_token: None,
+            connection_override: None,
+        }))
+    };
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    let capped = json_result(query(Some(100)).await.unwrap());
+    assert_eq!(capped["row_count"], 5);
+    assert_eq!(capped["truncated"], true);
+    let within = json_result(query(Some(3)).await.unwrap());
+    assert_eq!(within["row_count"], 3);
+    assert_eq!(within["truncated"], true);
+
+    let raw = json_result(execute("SELECT n FROM generate_series(1, 20) AS n").await.unwrap());
+    assert_eq!(raw["count"], 5);
+    assert_eq!(raw["truncated"], true);
+    let exact = json_result(execute("SELECT n FROM generate_series(1, 5) AS n").await.unwrap());
+    assert_eq!(exact["count"], 5);
+    assert!(exact.get("truncated").is_none());
+
+    execute("DROP TABLE IF EXISTS rmcp_test_max_result_rows").await.unwrap();
+    execute("CREATE TABLE rmcp_test_max_result_rows AS SELECT n FROM generate_series(1, 20) AS n")
+        .await
+        .unwrap();
+    let sample = server
+        .get_table_sample(Parameters(TableSampleParams {
+            table_name: "rmcp_test_max_result_rows".to_string(),
+            limit: Some(50),
+            exclude_columns: None,
+            offset: None,
+            order_by: None,
+            where_conditions: None,
+        }))
+        .await;
+    let export = server
+        .export_ndjson(Parameters(ExportParams {
+            query: None,
+            table_name: Some("rmcp_test_max_result_rows".to_string()),
+            limit: Some(100),
+        }))
+        .await;
+    let inserts = server
+        .generate_inserts(Parameters(GenerateInsertsParams {
+            table_name: Some("rmcp_test_max_result_rows".to_string()),
+            where_conditions: None,
+            query: None,
+            target_table: None,
+            limit: Some(100),
+        }))
+        .await;
+    execute("DROP TABLE rmcp_test_max_result_rows").await.unwrap();
+
+    let sample = json_result(sample.unwrap());
+    assert_eq!(sample["count"], 5);
+    assert_eq!(sample["has_more"], true);
+
+    let export = export.unwrap();
+    let lines: Vec<&str> = export.content[0].as_text().unwrap().text.lines().collect();
+    assert_eq!(lines.len(), 6);
+    let marker: serde_json::Value = serde_json::from_str(lines[5]).unwrap();
+    assert_eq!(marker["_truncated"], true);
+    assert!(marker["message"].as_str().unwrap().contains("server limit"));
+
+    let inserts = json_result(inserts.unwrap());
+    assert_eq!(inserts["row_count"], 5);
+    assert_eq!(inserts["truncated"], true);
+
+    let batch = json_result(
+        server
+            .query_stream(Parameters(QueryStreamParams {
+                query: Some("SELECT n FROM generate_series(1, 20) AS n".to_string()),
+                cursor_id: None,
+                batch_size: Some(100),
+                close: None,
+            }))
+            .await
+            .unwrap(),
+    );
+    assert_eq!(batch["row_count"], 5);
+    assert!(batch["cursor_id"].is_string());
+    server
+        .query_stream(Parameters(QueryStreamParams {
+            query: None,
+            cursor_id: batch["cursor_id"].as_str().map(str::to_string),
+            batch_size: None,
+            close: Some(true),
+        }))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_sql_error_data() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_error_data").await.unwrap();
+    execute("CREATE TABLE rmcp_test_error_data (id int PRIMARY KEY, email text CONSTRAINT rmcp_test_error_data_email_key UNIQUE)")
+        .await
+        .unwrap();
+    execute("INSERT INTO rmcp_test_error_data VALUES (1, 'a@example.com')").await.unwrap();
+
+    let duplicate = server
+        .insert_data(Parameters(InsertParams {
+            table_name: "rmcp_test_error_data".to_string(),
+            data: serde_json::json!({"id": 2, "email": "a@example.com"}),
+            returning: None,
+            connection_override: None,
+        }))
+        .await;
+    let syntax = execute("SELEC 1").await;
+
+    execute("DROP TABLE rmcp_test_error_data").await.unwrap();
+
+    let data = duplicate.unwrap_err().data.expect("error data");
+    assert_eq!(data["sqlstate"], "23505");
+    assert_eq!(data["constraint"], "rmcp_test_error_data_email_key");
+    assert_eq!(data["schema"], "public");
+    assert_eq!(data["table"], "rmcp_test_error_data");
+    assert!(data["detail"].as_str().unwrap().contains("a@example.com"), "{}", data);
+    assert!(data["message"].as_str().unwrap().contains("duplicate key value"), "{}", data);
+
+    let syntax = syntax.unwrap_err();
+    assert!(syntax.message.contains("syntax error at or near \"SELEC\""), "{}", syntax.message);
+    assert_eq!(
+        syntax.data.expect("error data"),
+        serde_json::json!({"sqlstate": "42601", "message": "syntax error at or near \"SELEC\"", "position": 1})
+    );
+}
+
+#[tokio::test]
+async fn test_unix_socket_connection() {
+    let Ok(connection_string) = std::env::var("POSTGRES_TEST_CONNECTION_STRING") else {
+        return;
+    };
+    let config: tokio_postgres::Config = connection_string.parse().unwrap();
+    let socket_dir = std::env::var("POSTGRES_TEST_SOCKET_DIR").unwrap_or_else(|_| "/var/run/postgresql".to_string());
+    let port = config.get_ports().first().copied().unwrap_or(5432);
+    if !std::path::Path::new(&socket_dir).join(format!(".s.PGSQL.{}", port)).exists() {
+        return;
+    }
+
+    let mut socket_config = format!(
+        "host={} port={} user={} dbname={}",
+        socket_dir,
+        port,
+        config.get_user().unwrap_or("postgres"),
+        config.get_dbname().or(config.get_user()).unwrap_or("postgres")
+    );
+    if let Some(password) = config.get_password() {
+        socket_config.push_str(&format!(" password='{}'", String::from_utf8_lossy(password)));
+    }
+
+    for sslmode in ["prefer", "disable"] {
+        let server = PostgresServer::new(format!("{} sslmode={}", socket_config, sslmode));
+        let result = server
+            .query_data(Parameters(QueryParams {
+                query: "SELECT 1 AS one".to_string(),
+                max_rows: None,
+                format: None,
+                include_types: None,
+                null_token: None,
+                connection_override: None,
+            }))
+            .await;
+        assert_eq!(
+            json_result(result.unwrap())["rows"],
+            serde_json::json!([{"one": 1}]),
+            "sslmode={}",
+            sslmode
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_benchmark_query_accepts_any_query() {
+    let Some(server) = test_server() else { return };
+    let benchmark = |query: &str| {
+        server.benchmark_query(Parameters(BenchmarkParams {
+            query: query.to_string(),
+            iterations: Some(2),
+            timeout_ms: None,
+        }))
+    };
+
+    benchmark("-- note\nSELECT 1").await.unwrap();
+    benchmark("VALUES (1), (2)").await.unwrap();
+    benchmark("TABLE pg_am").await.unwrap();
+    let rejected = benchmark("DELETE FROM pg_am").await.unwrap_err();
+    assert!(rejected.message.contains("only supports queries"), "{}", rejected.message);
+}
+
+#[tokio::test]
+async fn test_public_table_tools_ignore_search_path() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let table = || "rmcp_test_shadowed".to_string();
+
+    execute("DROP SCHEMA IF EXISTS rmcp_test_shadow CASCADE").await.unwrap();
+    execute("DROP TABLE IF EXISTS rmcp_test_shadowed").await.unwrap();
+    execute("CREATE TABLE rmcp_test_shadowed (id int, secret text)").await.unwrap();
+    execute("INSERT INTO rmcp_test_shadowed VALUES (1, 'public')").await.unwrap();
+    execute("CREATE SCHEMA rmcp_test_shadow").await.unwrap();
+    execute("CREATE TABLE rmcp_test_shadow.rmcp_test_shadowed (id int, other text)").await.unwrap();
+    execute("INSERT INTO rmcp_test_shadow.rmcp_test_shadowed VALUES (2, 'shadow'), (3, 'shadow')").await.unwrap();
+
+    // A same-named table earlier on the search_path must not be read or written instead
+    server
+        .begin_transaction(Parameters(BeginTransactionParams { isolation_level: None }))
+        .await
+        .unwrap();
+    execute("SET LOCAL search_path = rmcp_test_shadow, public").await.unwrap();
+    let sample = server
+        .get_table_sample(Parameters(TableSampleParams {
+            table_name: table(),
+            limit: None,
+            exclude_columns: Some(vec!["secret".to_string()]),
+            offset: None,
+            order_by: Some("id".to_string()),
+            where_conditions: None,
+        }))
+        .await;
+    let inserted = server
+        .insert_data(Parameters(InsertParams {
+            table_name: table(),
+            data: serde_json::json!({"id": 4, "secret": "inserted"}),
+            returning: Some(vec!["secret".to_string()]),
+            connection_override: None,
+        }))
+        .await;
+    let updated = server
+        .update_data(Parameters(UpdateDataParams {
+            table_name: table(),
+            values: serde_json::json!({"secret": "updated"}),
+            where_conditions: serde_json::json!({"id": 1}),
+            limit: None,
+            returning: None,
+            connection_override: None,
+        }))
+        .await;
+    let counted = server
+        .count_rows(Parameters(CountRowsParams {
+            table_name: table(),
+            where_conditions: None,
+            approximate: None,
+        }))
+        .await;
+    let deleted = server
+        .delete_data(Parameters(DeleteDataParams {
+            table_name: table(),
+            where_conditions: serde_json::json!({"id": 1}),
+            limit: None,
+            returning: None,
+            connection_override: None,
+        }))
+        .await;
+    server.rollback_transaction().await.unwrap();
+
+    execute("DROP SCHEMA rmcp_test_shadow CASCADE").await.unwrap();
+    execute("DROP TABLE rmcp_test_shadowed").await.unwrap();
+
+    assert_eq!(json_result(sample.unwrap())["rows"], serde_json::json!([{"id": 1}]));
+    assert_eq!(json_result(inserted.unwrap())["rows"], serde_json::json!([{"secret": "inserted"}]));
+    assert_eq!(json_result(updated.unwrap())["rows_affected"], 1);
+    assert_eq!(json_result(counted.unwrap())["count"], 2);
+    assert_eq!(json_result(deleted.unwrap())["rows_affected"], 1);
+}
+
+#[tokio::test]
+async fn test_serialization_failure_in_transaction_is_not_retried() {
+    let Some(server) = test_server().map(|server| server.with_serialization_retries(3)) else { return };
+    let Some(other) = test_server() else { return };
+
+    let in_session = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let execute = |query: &str| {
+        other.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_serialization").await.unwrap();
+    execute("CREATE TABLE rmcp_test_serialization (id int PRIMARY KEY, n int)").await.unwrap();
+    execute("INSERT INTO rmcp_test_serialization VALUES (1, 0)").await.unwrap();
+
+    server
+        .begin_transaction(Parameters(BeginTransactionParams {
+            isolation_level: Some("repeatable read".to_string()),
+        }))
+        .await
+        .unwrap();
+    // Take the snapshot, then let another connection change the row underneath it
+    in_session("SELECT n FROM rmcp_test_serialization").await.unwrap();
+    execute("UPDATE rmcp_test_serialization SET n = 1").await.unwrap();
+    let conflicting = server
+        .update_data(Parameters(UpdateDataParams {
+            table_name: "rmcp_test_serialization".to_string(),
+            values: serde_json::json!({"n": 2}),
+            where_conditions: serde_json::json!({"id": 1}),
+            limit: None,
+            returning: None,
+            connection_override: None,
+        }))
+        .await;
+    server.rollback_transaction().await.unwrap();
+
+    execute("DROP TABLE rmcp_test_serialization").await.unwrap();
+
+    let err = conflicting.unwrap_err();
+    assert_eq!(err.data.as_ref().unwrap()["sqlstate"], "40001", "{}", err.message);
+    assert!(err.message.contains("retry the whole transaction"), "{}", err.message);
+}
+
+#[tokio::test]
+async fn test_generate_inserts_where_conditions() {
+    let Some(server) = test_server() else { return };
+
+    let execute = |query: &str| {
+        server.execute_raw_query(Parameters(ExecuteRawQueryParams {
+            query: query.to_string(),
+            params: None,
+            connection_override: None,
+        }))
+    };
+    let generate = |where_conditions: serde_json::Value| {
+        server.generate_inserts(Parameters(GenerateInsertsParams {
+            table_name: Some("rmcp_test_generate".to_string()),
+            where_conditions: Some(where_conditions),
+            query: None,
+            target_table: None,
+            limit: None,
+        }))
+    };
+
+    execute("DROP TABLE IF EXISTS rmcp_test_generate").await.unwrap();
+    execute("DROP TYPE IF EXISTS rmcp_test_generate_status").await.unwrap();
+    execute("CREATE TYPE rmcp_test_generate_status AS ENUM ('open', 'closed')").await.unwrap();
+    execute(
+        "CREATE TABLE rmcp_test_generate
+         (id int, day date, amount numeric, ref uuid, status rmcp_test_generate_status)",
+    )
+    .await
+    .unwrap();
+    execute(
+        "INSERT INTO rmcp_test_generate VALUES
+         (1, '2024-01-01', 1.50, 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', 'open'),
+         (2, '2024-01-02', 2.50, 'b0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', 'closed')",
+    )
+    .await
+    .unwrap();
+
+    let by_date = generate(serde_json::json!({"day": "2024-01-02"})).await;
+    let by_numeric = generate(serde_json::json!({"amount": 1.5})).await;
+    let by_uuid = generate(serde_json::json!({"ref": "b0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11"})).await;
+    let by_enum = generate(serde_json::json!({"status": "open", "id": 1})).await;
+
+    execute("DROP TABLE rmcp_test_generate").await.unwrap();
+    execute("DROP TYPE rmcp_test_generate_status").await.unwrap();
+
+    for (result, id) in [(by_date, 2), (by_numeric, 1), (by_uuid, 2), (by_enum, 1)] {
+        let result = json_result(result.unwrap());
+        assert_eq!(result["row_count"], 1, "{}", result);
+        assert!(
+            result["sql"].as_str().unwrap().contains(&format!("VALUES ({}, ", id)),
+            "{}",
+            result
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_advisory_lock_held_by_prepared_transaction() {
+    let Ok(connection_string) = std::env::var("POSTGRES_TEST_CONNECTION_STRING") else {
+        return;
+    };
+    let server = PostgresServer::new(&connection_string);
+    let (client, connection) = tokio_postgres::connect(&connection_string, tokio_postgres::NoTls)
+        .await
+        .unwrap();
+    tokio::spawn(connection);
+    // PREPARE TRANSACTION is disabled unless max_prepared_transactions is raised
+    let enabled: String = client.query_one("SHOW max_prepared_transactions", &[]).await.unwrap().get(0);
+    if enabled == "0" {
+        return;
+    }
+
+    client
+        .batch_execute(
+            "BEGIN;
+             SELECT pg_advisory_xact_lock(7416416);
+             PREPARE TRANSACTION 'rmcp_test_advisory'",
+        )
+        .await
+        .unwrap();
+    let result = server.get_advisory_locks().await;
+    client.batch_execute("ROLLBACK PREPARED 'rmcp_test_advisory'").await.unwrap();
+
+    let result = json_result(result.unwrap());
+    let lock = result["locks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|lock| lock["lock"]["key"] == 7416416)
+        .expect("prepared transaction's lock is listed");
+    assert_eq!(lock["pid"], serde_json::Value::Null);
+    assert_eq!(lock["prepared_transaction"], "rmcp_test_advisory");
+    assert_eq!(lock["granted"], true);
+}